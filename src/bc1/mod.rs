@@ -43,8 +43,6 @@ use super::{Result, Error};
 use std::mem;
 use std::slice;
 
-// TODO: support mipmaps
-
 #[repr(C)]
 #[derive(Copy, Clone)]
 struct Block {
@@ -56,6 +54,12 @@ struct Block {
 
 impl Block {
 	fn colors(&self) -> [R8G8B8; 4] {
+		self.colors_with(false)
+	}
+
+	// When `force_four` is set the 4-color (opaque) palette is always used, regardless of endpoint
+	// ordering. This is how BC2 and BC3 interpret their embedded color block.
+	fn colors_with(&self, force_four: bool) -> [R8G8B8; 4] {
 		let c0 = self.color0.as_r8g8b8();
 		let c1 = self.color1.as_r8g8b8();
 
@@ -65,13 +69,15 @@ impl Block {
 		let c2;
 		let c3;
 
-		if c0.as_u32() > c1.as_u32() {
+		if force_four || c0.as_u32() > c1.as_u32() {
 			let lerp = |el0, el1| ((2 * el0 as u16 + el1 as u16) / 3) as u8;
 
 			c2 = R8G8B8(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1));
 			c3 = R8G8B8(lerp(r1, r0), lerp(g1, g0), lerp(b1, b0));
 		} else {
-			c2 = R8G8B8((r0 + r1) / 2, (g0 + g1) / 2, (b0 + b1) / 2);
+			let avg = |el0, el1| ((el0 as u16 + el1 as u16) / 2) as u8;
+
+			c2 = R8G8B8(avg(r0, r1), avg(g0, g1), avg(b0, b1));
 			c3 = R8G8B8(0, 0, 0);
 		}
 
@@ -97,6 +103,14 @@ impl R5G6B5 {
 
 		R8G8B8(r8, g8, b8)
 	}
+
+	fn from_r8g8b8(color: R8G8B8) -> R5G6B5 {
+		let r = (color.0 as u16 >> 3) & ((1 << 5) - 1);
+		let g = (color.1 as u16 >> 2) & ((1 << 6) - 1);
+		let b = (color.2 as u16 >> 3) & ((1 << 5) - 1);
+
+		R5G6B5((r << 11) | (g << 5) | b)
+	}
 }
 
 #[repr(C, packed)]
@@ -121,18 +135,13 @@ pub fn decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
 		slice::from_raw_parts(ptr, blocks)
 	};
 
-	let output_len = calculate_output_len(data.len());
-
-	let mut output = Vec::with_capacity(output_len);
-
-	unsafe {
-		output.set_len(output_len);
-	}
+	// Three bytes per pixel; the decoded image is exactly `width` by `height`, with edge tiles
+	// clamped so levels whose dimensions are not a multiple of 4 still decode to the right size.
+	let mut output = vec![0u8; (width * height * 3) as usize];
 
 	let dest = unsafe {
-		let ptr = mem::transmute::<*const u8, *mut R8G8B8>(output.as_mut_ptr());
-		let pixels = output_len / 24;
-		slice::from_raw_parts_mut(ptr, pixels)
+		let ptr = output.as_mut_ptr() as *mut R8G8B8;
+		slice::from_raw_parts_mut(ptr, (width * height) as usize)
 	};
 
 	decode_internal(src, width, height, dest);
@@ -140,37 +149,178 @@ pub fn decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
 	Ok(output)
 }
 
-fn calculate_output_len(input_len: usize) -> usize {
-	(input_len / BLOCK_SIZE)
-		// 4x4 pixels per block
-		* 16
-		// 3 component pixels, 1 byte / component.
-		* 24
-}
+/// Encodes an uncompressed R8-G8-B8 image into BC1 (DXT1) blocks.
+///
+/// The input must contain `width * height` three-byte pixels. The image is split into 4x4 tiles
+/// (edge tiles are clamped to the image bounds), and each tile is turned into one 8-byte [`Block`].
+///
+/// Endpoints are chosen with the color-bounding-box method: the per-channel minimum and maximum
+/// across the sixteen pixels define a box, which is inset by 1/16 of its range to cut down on
+/// banding. Both corners are quantized to [`R5G6B5`] and ordered so that `color0 >= color1`; when
+/// they differ this selects the opaque 4-color palette, and when both quantize to the same color the
+/// 3-color palette is used, whose first entry is that color. Each pixel then picks the palette entry minimizing the
+/// squared RGB distance, and the indices are packed in the same order [`decode_internal`] reads.
+pub fn encode(rgb: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+	let expected = (width * height * 3) as usize;
+	if rgb.len() != expected {
+		let msg = format!("BC1 input size mismatch. Expected: {} bytes, found: {} bytes.", expected, rgb.len());
+		return Err(Error::FormatError(msg));
+	}
 
-fn decode_internal(data: &[Block], width: u32, height: u32, output: &mut [R8G8B8]) {
 	let columns = columns(width);
 	let rows = rows(height);
 
-	let get_block = |x, y| &data[(x * columns + y) as usize];
-	let mut set_pixel = |x, y, value| output[(x * width + y) as usize] = value;
+	let mut blocks: Vec<Block> = Vec::with_capacity((columns * rows) as usize);
+
+	let get_pixel = |x: u32, y: u32| {
+		// Clamp edge tiles to the last valid pixel.
+		let x = x.min(height - 1);
+		let y = y.min(width - 1);
+
+		let base = ((x * width + y) * 3) as usize;
 
-	let mut out_x = 0;
-	let mut out_y = 0;
+		R8G8B8(rgb[base], rgb[base + 1], rgb[base + 2])
+	};
 
-	// Each 8-byte block unpacks to a 4x4 pixel area.
-	// - (row, column) index the blocks.
-	// - (out_x, out_y) index the pixels in the output image.
 	for row in 0..rows {
 		for column in 0..columns {
-			let block = get_block(row, column);
+			let out_x = row * 4;
+			let out_y = column * 4;
+
+			// Find the bounding box of the tile's colors.
+			let mut min = R8G8B8(255, 255, 255);
+			let mut max = R8G8B8(0, 0, 0);
+
+			for offset_x in 0..4 {
+				for offset_y in 0..4 {
+					let pixel = get_pixel(out_x + offset_x, out_y + offset_y);
+
+					min.0 = min.0.min(pixel.0);
+					min.1 = min.1.min(pixel.1);
+					min.2 = min.2.min(pixel.2);
+
+					max.0 = max.0.max(pixel.0);
+					max.1 = max.1.max(pixel.1);
+					max.2 = max.2.max(pixel.2);
+				}
+			}
+
+			// Inset the box by 1/16 of its range to reduce banding.
+			let inset = |lo: u8, hi: u8| {
+				let range = (hi - lo) as i32 / 16;
+				(
+					(lo as i32 + range).min(255) as u8,
+					(hi as i32 - range).max(0) as u8
+				)
+			};
+
+			let (min_r, max_r) = inset(min.0, max.0);
+			let (min_g, max_g) = inset(min.1, max.1);
+			let (min_b, max_b) = inset(min.2, max.2);
+
+			let mut color0 = R5G6B5::from_r8g8b8(R8G8B8(max_r, max_g, max_b));
+			let mut color1 = R5G6B5::from_r8g8b8(R8G8B8(min_r, min_g, min_b));
+
+			// Ordering the endpoints so that `color0 > color1` selects the opaque 4-color palette.
+			// Equal endpoints are left as they are.
+			if color0.as_r8g8b8().as_u32() < color1.as_r8g8b8().as_u32() {
+				mem::swap(&mut color0, &mut color1);
+			}
+
+			let mut block = Block {
+				color0, color1,
+				indices: 0
+			};
 
 			let colors = block.colors();
 
-			const MASK: u32 = (1 << 2) - 1;
+			let mut n = 0;
+
+			for offset_x in 0..4 {
+				for offset_y in 0..4 {
+					let pixel = get_pixel(out_x + offset_x, out_y + offset_y);
+
+					let mut best = 0;
+					let mut best_distance = u32::max_value();
+
+					for (index, color) in colors.iter().enumerate() {
+						let distance = squared_distance(pixel, *color);
+
+						if distance < best_distance {
+							best = index as u32;
+							best_distance = distance;
+						}
+					}
+
+					block.indices |= best << (n * 2);
+					n += 1;
+				}
+			}
+
+			blocks.push(block);
+		}
+	}
+
+	let output = unsafe {
+		let ptr = blocks.as_ptr() as *const u8;
+		slice::from_raw_parts(ptr, blocks.len() * BLOCK_SIZE).to_vec()
+	};
+
+	Ok(output)
+}
+
+fn squared_distance(a: R8G8B8, b: R8G8B8) -> u32 {
+	let dr = a.0 as i32 - b.0 as i32;
+	let dg = a.1 as i32 - b.1 as i32;
+	let db = a.2 as i32 - b.2 as i32;
+
+	(dr * dr + dg * dg + db * db) as u32
+}
+
+// Reconstructs the sixteen RGB colors of a single 8-byte color block, in the same pixel order
+// `decode_internal` unpacks. `force_four` selects the opaque 4-color palette and is set by BC2/BC3,
+// which embed a BC1 color block but never use its 1-bit alpha mode.
+pub(crate) fn decode_color_block(data: &[u8], force_four: bool) -> [[u8; 3]; 16] {
+	let block = unsafe { &*(data.as_ptr() as *const Block) };
+
+	let colors = block.colors_with(force_four);
 
+	const MASK: u32 = (1 << 2) - 1;
+
+	let mut output = [[0u8; 3]; 16];
+
+	for (n, pixel) in output.iter_mut().enumerate() {
+		let index = (block.indices >> (n * 2)) & MASK;
+		let color = colors[index as usize];
+
+		*pixel = [color.0, color.1, color.2];
+	}
+
+	output
+}
+
+fn decode_internal(data: &[Block], width: u32, height: u32, output: &mut [R8G8B8]) {
+	let columns = columns(width) as usize;
+	let width = width as usize;
+	let height = height as usize;
+
+	// Each row of blocks fills one strip, up to 4 pixels tall and `width` wide. The strips are
+	// disjoint regions of the output, so they can be filled in any order, in parallel. The final
+	// strip is shorter when `height` is not a multiple of 4.
+	let stride = width * 4;
+
+	// Fills the strip belonging to block row `row` from the blocks on that row.
+	let fill_strip = |row: usize, strip: &mut [R8G8B8]| {
+		const MASK: u32 = (1 << 2) - 1;
+
+		for column in 0..columns {
+			let block = &data[row * columns + column];
+
+			let colors = block.colors();
 			let indices = block.indices;
 
+			let out_y = column * 4;
+
 			let mut n = 0;
 
 			for offset_x in 0..4 {
@@ -178,17 +328,34 @@ fn decode_internal(data: &[Block], width: u32, height: u32, output: &mut [R8G8B8
 					let index = (indices >> (n * 2)) & MASK;
 					n += 1;
 
-					set_pixel(out_x + offset_x, out_y + offset_y, colors[index as usize]);
+					let x = out_y + offset_y;
+
+					// Edge tiles hang over the image bounds; skip the pixels that fall outside.
+					if row * 4 + offset_x < height && x < width {
+						// Index relative to the start of this block row's strip.
+						let pixel = offset_x * width + x;
+						strip[pixel] = colors[index as usize];
+					}
 				}
 			}
-
-			// Advance one block to the right.
-			out_y += 4;
 		}
+	};
+
+	#[cfg(feature = "parallel")]
+	{
+		use rayon::prelude::*;
+
+		output
+			.par_chunks_mut(stride)
+			.enumerate()
+			.for_each(|(row, strip)| fill_strip(row, strip));
+	}
 
-		// Start unpacking a new line.
-		out_x += 4;
-		out_y = 0;
+	#[cfg(not(feature = "parallel"))]
+	{
+		for (row, strip) in output.chunks_mut(stride).enumerate() {
+			fill_strip(row, strip);
+		}
 	}
 }
 
@@ -208,3 +375,47 @@ fn columns(width: u32) -> u32 {
 fn rows(height: u32) -> u32 {
 	clamp_non_zero((height + 3) / 4 )
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn encode_produces_block_sized_output() {
+		let rgb = vec![0u8; 4 * 4 * 3];
+
+		let encoded = encode(&rgb, 4, 4).unwrap();
+
+		assert_eq!(encoded.len(), BLOCK_SIZE);
+	}
+
+	#[test]
+	fn encode_rejects_wrong_input_size() {
+		let rgb = vec![0u8; 10];
+
+		assert!(encode(&rgb, 4, 4).is_err());
+	}
+
+	#[test]
+	fn round_trip_solid_color() {
+		let (width, height) = (4, 4);
+
+		// A single, flat color quantizes both endpoints to the same value.
+		let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+		for _ in 0..(width * height) {
+			rgb.extend_from_slice(&[136, 72, 200]);
+		}
+
+		let encoded = encode(&rgb, width, height).unwrap();
+		let decoded = decode(&encoded, width, height).unwrap();
+
+		// The decoded image must be exactly `width * height` three-byte pixels.
+		assert_eq!(decoded.len(), (width * height * 3) as usize);
+
+		// Quantizing to R5G6B5 and back is lossy, so compare within the rounding error.
+		for (original, result) in rgb.iter().zip(decoded.iter()) {
+			let delta = (*original as i32 - *result as i32).abs();
+			assert!(delta <= 8, "channel drifted by {}", delta);
+		}
+	}
+}
@@ -0,0 +1,111 @@
+//! # Reference
+//!
+//! - [BC2 on Microsoft Docs](https://docs.microsoft.com/en-us/windows/uwp/graphics-concepts/block-compression#bc2)
+//! - [Nathan Reed's article](http://reedbeta.com/blog/understanding-bcn-texture-compression-formats/#bc2-and-bc3)
+//!
+//! # Algorithm information
+//! BC2 stores RGBA data in 16-byte blocks. The first 8 bytes hold sixteen explicit 4-bit alpha
+//! values, one per pixel, and the remaining 8 bytes are a BC1 color block (always using the opaque
+//! 4-color palette). Because alpha is stored verbatim it handles sharp transitions the interpolated
+//! BC3 alpha cannot, at the cost of only 4 bits of precision.
+
+use super::{Result, Error};
+use bc1;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Decodes an image compressed with the BC2 algorithm into an RGBA image.
+pub fn decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+	if data.len() % BLOCK_SIZE != 0 {
+		return Err(Error::FormatError("Length of BC2 data to decode is not a multiple of the block size.".to_string()));
+	}
+
+	let mut output = vec![0u8; (width * height * 4) as usize];
+
+	let columns = columns(width);
+	let rows = rows(height);
+
+	for row in 0..rows {
+		for column in 0..columns {
+			let offset = ((row * columns + column) as usize) * BLOCK_SIZE;
+
+			let alpha = &data[offset..offset + 8];
+			let colors = bc1::decode_color_block(&data[offset + 8..offset + BLOCK_SIZE], true);
+
+			let out_x = row * 4;
+			let out_y = column * 4;
+
+			let mut n = 0;
+
+			for offset_x in 0..4 {
+				for offset_y in 0..4 {
+					let out_x = out_x + offset_x;
+					let out_y = out_y + offset_y;
+
+					// Edge tiles hang over the image bounds; skip the pixels that fall outside.
+					if out_x < height && out_y < width {
+						// Each byte of the alpha block holds two 4-bit values, expanded to 8 bits.
+						let nibble = (alpha[n / 2] >> ((n % 2) * 4)) & 0xf;
+						let a = nibble * 17;
+
+						let color = colors[n];
+
+						let base = ((out_x * width + out_y) * 4) as usize;
+						output[base] = color[0];
+						output[base + 1] = color[1];
+						output[base + 2] = color[2];
+						output[base + 3] = a;
+					}
+
+					n += 1;
+				}
+			}
+		}
+	}
+
+	Ok(output)
+}
+
+use std::cmp::max;
+
+fn columns(width: u32) -> u32 {
+	max(1, (width + 3) / 4)
+}
+
+fn rows(height: u32) -> u32 {
+	max(1, (height + 3) / 4)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_rejects_wrong_input_size() {
+		let data = vec![0u8; 10];
+
+		assert!(decode(&data, 4, 4).is_err());
+	}
+
+	#[test]
+	fn decode_solid_white() {
+		// A block of fully opaque white: explicit alpha nibbles all `0xf`, and a color block whose
+		// two endpoints are both white with all indices pointing at the first palette entry.
+		let mut block = vec![0xffu8; 8];
+		block.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+
+		let decoded = decode(&block, 4, 4).unwrap();
+
+		assert_eq!(decoded, vec![255u8; 4 * 4 * 4]);
+	}
+
+	#[test]
+	fn decode_clamps_non_multiple_dimensions() {
+		// A single block covering a 3x3 image must not write past the tightly-sized output.
+		let block = vec![0u8; BLOCK_SIZE];
+
+		let decoded = decode(&block, 3, 3).unwrap();
+
+		assert_eq!(decoded.len(), 3 * 3 * 4);
+	}
+}
@@ -0,0 +1,142 @@
+//! # Reference
+//!
+//! - [BC4 on Microsoft Docs](https://docs.microsoft.com/en-us/windows/uwp/graphics-concepts/block-compression#bc4)
+//! - [Nathan Reed's article](http://reedbeta.com/blog/understanding-bcn-texture-compression-formats/#bc4-and-bc5)
+//!
+//! # Algorithm information
+//! BC4 stores a single channel, such as a height, gloss or coverage map.
+//!
+//! Each 8-byte block describes a 4x4 pixel area: two 8-bit endpoints `a0`, `a1`, followed by sixteen
+//! 3-bit indices into an interpolated palette. If `a0 > a1` the palette holds the two endpoints plus
+//! six evenly-spaced interpolants; otherwise it holds the two endpoints, four interpolants and the
+//! fixed values `0` and `255`.
+
+use super::{Result, Error};
+
+const BLOCK_SIZE: usize = 8;
+
+/// Decodes an image compressed with the BC4 algorithm into a single-channel image.
+pub fn decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+	if data.len() % BLOCK_SIZE != 0 {
+		return Err(Error::FormatError("Length of BC4 data to decode is not a multiple of the block size.".to_string()));
+	}
+
+	let mut output = vec![0u8; (width * height) as usize];
+
+	let columns = columns(width);
+	let rows = rows(height);
+
+	for row in 0..rows {
+		for column in 0..columns {
+			let offset = ((row * columns + column) as usize) * BLOCK_SIZE;
+
+			let values = decode_alpha_block(&data[offset..offset + BLOCK_SIZE]);
+
+			let out_x = row * 4;
+			let out_y = column * 4;
+
+			for offset_x in 0..4 {
+				for offset_y in 0..4 {
+					let out_x = out_x + offset_x;
+					let out_y = out_y + offset_y;
+
+					// Edge tiles hang over the image bounds; skip the pixels that fall outside.
+					if out_x >= height || out_y >= width {
+						continue;
+					}
+
+					let n = (offset_x * 4 + offset_y) as usize;
+
+					output[(out_x * width + out_y) as usize] = values[n];
+				}
+			}
+		}
+	}
+
+	Ok(output)
+}
+
+// Reconstructs the sixteen values of a single 8-byte interpolated block. Shared by BC3's alpha block
+// and by both channels of BC5.
+pub(crate) fn decode_alpha_block(data: &[u8]) -> [u8; 16] {
+	let a0 = data[0];
+	let a1 = data[1];
+
+	let mut palette = [0u8; 8];
+	palette[0] = a0;
+	palette[1] = a1;
+
+	let lerp = |numerator: u16, a: u8, b: u8, denominator: u16| {
+		((numerator * a as u16 + (denominator - numerator) * b as u16) / denominator) as u8
+	};
+
+	if a0 > a1 {
+		// Six interpolated values between the endpoints.
+		for i in 1..7 {
+			palette[(i + 1) as usize] = lerp(7 - i, a0, a1, 7);
+		}
+	} else {
+		// Four interpolated values, plus the fixed `0` and `255`.
+		for i in 1..5 {
+			palette[(i + 1) as usize] = lerp(5 - i, a0, a1, 5);
+		}
+		palette[6] = 0;
+		palette[7] = 255;
+	}
+
+	// The sixteen 3-bit indices are packed little-endian into the remaining six bytes.
+	let mut indices: u64 = 0;
+	for i in 0..6 {
+		indices |= (data[2 + i] as u64) << (8 * i);
+	}
+
+	let mut output = [0u8; 16];
+	for (n, value) in output.iter_mut().enumerate() {
+		let index = ((indices >> (n * 3)) & 0x7) as usize;
+		*value = palette[index];
+	}
+
+	output
+}
+
+use std::cmp::max;
+
+fn columns(width: u32) -> u32 {
+	max(1, (width + 3) / 4)
+}
+
+fn rows(height: u32) -> u32 {
+	max(1, (height + 3) / 4)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_rejects_wrong_input_size() {
+		let data = vec![0u8; 5];
+
+		assert!(decode(&data, 4, 4).is_err());
+	}
+
+	#[test]
+	fn decode_solid_value() {
+		// Endpoints `200`/`100` (so `a0 > a1`) with all indices at `0` select the first endpoint.
+		let block = vec![200u8, 100, 0, 0, 0, 0, 0, 0];
+
+		let decoded = decode(&block, 4, 4).unwrap();
+
+		assert_eq!(decoded, vec![200u8; 4 * 4]);
+	}
+
+	#[test]
+	fn decode_clamps_non_multiple_dimensions() {
+		// A single block covering a 3x3 image must not write past the tightly-sized output.
+		let block = vec![0u8; BLOCK_SIZE];
+
+		let decoded = decode(&block, 3, 3).unwrap();
+
+		assert_eq!(decoded.len(), 3 * 3);
+	}
+}
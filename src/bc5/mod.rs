@@ -0,0 +1,108 @@
+//! # Reference
+//!
+//! - [BC5 on Microsoft Docs](https://docs.microsoft.com/en-us/windows/uwp/graphics-concepts/block-compression#bc5)
+//! - [Nathan Reed's article](http://reedbeta.com/blog/understanding-bcn-texture-compression-formats/#bc4-and-bc5)
+//!
+//! # Algorithm information
+//! BC5 stores two independent channels in 16-byte blocks: two interpolated blocks (the same layout
+//! BC4 uses) laid out one after the other. It is most often used for tangent-space normal maps,
+//! storing the X and Y components and reconstructing Z in the shader.
+
+use super::{Result, Error};
+use bc4;
+
+const BLOCK_SIZE: usize = 16;
+
+/// Decodes an image compressed with the BC5 algorithm into a two-channel image.
+pub fn decode(data: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+	if data.len() % BLOCK_SIZE != 0 {
+		return Err(Error::FormatError("Length of BC5 data to decode is not a multiple of the block size.".to_string()));
+	}
+
+	let mut output = vec![0u8; (width * height * 2) as usize];
+
+	let columns = columns(width);
+	let rows = rows(height);
+
+	for row in 0..rows {
+		for column in 0..columns {
+			let offset = ((row * columns + column) as usize) * BLOCK_SIZE;
+
+			let red = bc4::decode_alpha_block(&data[offset..offset + 8]);
+			let green = bc4::decode_alpha_block(&data[offset + 8..offset + BLOCK_SIZE]);
+
+			let out_x = row * 4;
+			let out_y = column * 4;
+
+			for offset_x in 0..4 {
+				for offset_y in 0..4 {
+					let out_x = out_x + offset_x;
+					let out_y = out_y + offset_y;
+
+					// Edge tiles hang over the image bounds; skip the pixels that fall outside.
+					if out_x >= height || out_y >= width {
+						continue;
+					}
+
+					let n = (offset_x * 4 + offset_y) as usize;
+
+					let base = ((out_x * width + out_y) * 2) as usize;
+					output[base] = red[n];
+					output[base + 1] = green[n];
+				}
+			}
+		}
+	}
+
+	Ok(output)
+}
+
+use std::cmp::max;
+
+fn columns(width: u32) -> u32 {
+	max(1, (width + 3) / 4)
+}
+
+fn rows(height: u32) -> u32 {
+	max(1, (height + 3) / 4)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_rejects_wrong_input_size() {
+		let data = vec![0u8; 10];
+
+		assert!(decode(&data, 4, 4).is_err());
+	}
+
+	#[test]
+	fn decode_solid_values() {
+		// Two interpolated blocks: red endpoints `255`/`0`, green endpoints `100`/`0`, every index
+		// at `0`, so each pixel reads back as `(255, 100)`.
+		let mut block = vec![0u8; BLOCK_SIZE];
+		block[0] = 255;
+		block[8] = 100;
+
+		let decoded = decode(&block, 4, 4).unwrap();
+
+		let mut expected = Vec::with_capacity(4 * 4 * 2);
+		for _ in 0..(4 * 4) {
+			expected.extend_from_slice(&[255, 100]);
+		}
+
+		assert_eq!(decoded, expected);
+	}
+
+	#[test]
+	fn decode_clamps_non_multiple_dimensions() {
+		// A single block covering a 3x3 image must not write past the tightly-sized output.
+		let block = vec![0u8; BLOCK_SIZE];
+
+		let decoded = decode(&block, 3, 3).unwrap();
+
+		assert_eq!(decoded.len(), 3 * 3 * 2);
+	}
+}
@@ -1,37 +1,85 @@
-#[repr(C, packed)]
+use {Error, Result, BCAlgorithm};
+
+// The 20-byte extended header introduced by the DirectX 10 DDS format. It follows the base
+// `Header` whenever `PixelFormat.four_cc` equals `b"DX10"`.
+#[repr(C)]
 #[derive(Serialize, Deserialize)]
-struct HeaderExt {
-	// TODO: how to get DXGI_FORMAT?
+pub(crate) struct HeaderExt {
 	format: u32,
-	dimension: ResourceDimension,
-	misc_flags: MiscFlags1,
+	dimension: u32,
+	_misc_flags: u32,
 	// For a normal texture, this should be 1.
 	// For cubemaps, should be the number of cubes.
 	array_size: u32,
-	misc_flags2: MiscFlags2
+	misc_flags2: u32
 }
 
-#[repr(u32)]
-#[derive(Serialize, Deserialize)]
-enum ResourceDimension {
-	Texture1D = 2,
-	Texture2D = 3,
-	Texture3D = 4
-}
+impl HeaderExt {
+	// Decodes the `DXGI_FORMAT` into the matching block-compression algorithm, or reports an error
+	// for formats this crate cannot decode.
+	pub(crate) fn algorithm(&self) -> Result<BCAlgorithm> {
+		// See the `DXGI_FORMAT` enumeration in the DirectX headers.
+		match self.format {
+			70..=72 => Ok(BCAlgorithm::BC1),
+			73..=75 => Ok(BCAlgorithm::BC2),
+			76..=78 => Ok(BCAlgorithm::BC3),
+			79..=81 => Ok(BCAlgorithm::BC4),
+			82..=84 => Ok(BCAlgorithm::BC5),
+			97..=99 => Ok(BCAlgorithm::BC7),
+			other => Err(Error::FormatError(format!("Unsupported DXGI_FORMAT: {}", other)))
+		}
+	}
 
-bitflags! {
-	#[derive(Serialize, Deserialize)]
-	struct MiscFlags1: u32 {
-		const CUBEMAP_TEXTURE = 0x4;
+	pub(crate) fn array_size(&self) -> u32 {
+		self.array_size
 	}
-}
-bitflags! {
-	#[derive(Serialize, Deserialize)]
-	struct MiscFlags2: u32 {
-		const ALPHA_MODE_UNKNOWN = 0x0;
-		const ALPHA_MODE_STRAIGHT = 0x1;
-		const ALPHA_MODE_PREMULTIPLIED = 0x2;
-		const ALPHA_MODE_OPAQUE = 0x3;
-		const ALPHA_MODE_CUSTOM = 0x4;
+
+	pub(crate) fn dimension(&self) -> ResourceDimension {
+		match self.dimension {
+			2 => ResourceDimension::Texture1D,
+			4 => ResourceDimension::Texture3D,
+			_ => ResourceDimension::Texture2D
+		}
+	}
+
+	pub(crate) fn alpha_mode(&self) -> AlphaMode {
+		// The alpha mode is stored in the low 3 bits of the second misc-flags field. These modes are
+		// mutually exclusive, so match on the value rather than treating it as a set of flags.
+		let misc_flags2 = self.misc_flags2;
+
+		match misc_flags2 & 0x7 {
+			0x1 => AlphaMode::Straight,
+			0x2 => AlphaMode::Premultiplied,
+			0x3 => AlphaMode::Opaque,
+			0x4 => AlphaMode::Custom,
+			_ => AlphaMode::Unknown
+		}
 	}
 }
+
+/// The dimensionality of a texture resource stored in a DX10 DDS file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResourceDimension {
+	/// A one-dimensional texture.
+	Texture1D,
+	/// A two-dimensional texture. This is the default for legacy DDS files.
+	Texture2D,
+	/// A three-dimensional (volume) texture.
+	Texture3D
+}
+
+/// How the alpha channel of a DX10 DDS texture should be interpreted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlphaMode {
+	/// The alpha mode is unknown. This is the default for legacy DDS files.
+	Unknown,
+	/// The color channels are not premultiplied by alpha.
+	Straight,
+	/// The color channels are premultiplied by alpha.
+	Premultiplied,
+	/// The texture is fully opaque and the alpha channel carries unrelated data.
+	Opaque,
+	/// The alpha channel has an application-specific meaning.
+	Custom
+}
+
@@ -5,10 +5,12 @@
 
 use std::io;
 use std::mem;
+use std::cmp::max;
 use bincode;
 use super::{Error, Result};
+use BCAlgorithm;
 
-#[repr(C, packed)]
+#[repr(C)]
 #[derive(Serialize, Deserialize)]
 struct Header {
 	size: u32,
@@ -38,7 +40,7 @@ bitflags! {
 
 		// All DDS files should have these bits set.
 		// However, when reading, do not rely on other programs to write these.
-		const REQUIRED = CAPS.bits | HEIGHT.bits | WIDTH.bits | PIXEL_FORMAT.bits;
+		const REQUIRED = Self::CAPS.bits | Self::HEIGHT.bits | Self::WIDTH.bits | Self::PIXEL_FORMAT.bits;
 
 		const UNCOMPRESSED_PITCH = 0x8;
 		const COMPRESSED_PITCH = 0x80000;
@@ -81,7 +83,7 @@ bitflags! {
 	}
 }
 
-#[repr(C, packed)]
+#[repr(C)]
 #[derive(Serialize, Deserialize)]
 struct PixelFormat {
 	size: u32,
@@ -108,31 +110,100 @@ bitflags! {
 	}
 }
 
-/// A texture loaded from a DDS file.
-pub struct Texture {
+// A single surface in the mipmap chain.
+struct Level {
 	width: u32, height: u32,
 	data: Vec<u8>
 }
 
+/// A texture loaded from a DDS file.
+pub struct Texture {
+	// Set for block-compressed textures, `None` for uncompressed RGB(A) data.
+	algorithm: Option<BCAlgorithm>,
+	// The following three fields carry DX10 metadata, with sensible defaults for legacy files.
+	array_size: u32,
+	dimension: ResourceDimension,
+	alpha_mode: AlphaMode,
+	// The mipmap chain, ordered from the largest surface down. Always contains at least one level.
+	levels: Vec<Level>
+}
+
 impl Texture {
-	/// Returns the width and height of the texture.
+	// Builds a texture from its decoded mipmap chain. Used by container parsers outside this module
+	// (such as KTX) so they can hand back the same `Texture` type. `levels` is ordered largest surface first and
+	// must contain at least one entry.
+	pub(crate) fn from_levels(
+		algorithm: Option<BCAlgorithm>,
+		array_size: u32,
+		dimension: ResourceDimension,
+		alpha_mode: AlphaMode,
+		levels: Vec<(u32, u32, Vec<u8>)>
+	) -> Texture {
+		let levels = levels.into_iter()
+			.map(|(width, height, data)| Level { width, height, data })
+			.collect();
+
+		Texture { algorithm, array_size, dimension, alpha_mode, levels }
+	}
+
+	/// Returns the width and height of the top-level surface.
 	pub fn dimensions(&self) -> (u32, u32) {
-		(self.width, self.height)
+		(self.levels[0].width, self.levels[0].height)
+	}
+
+	/// Returns the block-compression algorithm used by the texture, if any.
+	pub fn algorithm(&self) -> Option<BCAlgorithm> {
+		self.algorithm
 	}
 
-	/// Returns a slice of the raw bytes of the texture.
-	pub fn as_raw(&self) -> &[u8] {
-		&self.data
+	/// Returns the number of textures in the array (`1` for a plain texture).
+	pub fn array_size(&self) -> u32 {
+		self.array_size
+	}
+
+	/// Returns the dimensionality of the texture resource.
+	pub fn dimension(&self) -> ResourceDimension {
+		self.dimension
+	}
+
+	/// Returns how the texture's alpha channel should be interpreted.
+	pub fn alpha_mode(&self) -> AlphaMode {
+		self.alpha_mode
+	}
+
+	/// Returns the number of mipmap levels, always at least one.
+	pub fn mipmap_levels(&self) -> usize {
+		self.levels.len()
+	}
+
+	/// Returns the raw bytes and dimensions of the `n`th mipmap level.
+	///
+	/// Level `0` is the largest surface. Panics if `n` is out of range.
+	pub fn level(&self, n: usize) -> (&[u8], u32, u32) {
+		let level = &self.levels[n];
+		(&level.data, level.width, level.height)
+	}
+
+	/// Returns a slice of the raw bytes of the top-level surface.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.levels[0].data
 	}
 }
 
 /// Additional features, added by the DirectX 10 DDS format.
-// mod ext;
+mod ext;
+
+pub use self::ext::{ResourceDimension, AlphaMode};
 
 /// Reads a DDS file.
-pub fn read(reader: &mut io::Read) -> Result<Texture> {
+///
+/// The returned [`Texture`] holds the surfaces exactly as stored in the file: for a
+/// block-compressed file the bytes are still compressed. Decoding is left to the caller, who can
+/// dispatch on [`Texture::algorithm`] and feed each [`Texture::level`] to the matching decoder
+/// (`bc1::decode`, `bc2::decode`, ...) with that level's dimensions.
+pub fn read(reader: &mut dyn io::Read) -> Result<Texture> {
 	{
-		let mut magic_number: [u8; 4] = unsafe { mem::uninitialized() };
+		let mut magic_number = [0u8; 4];
 
 		reader.read_exact(&mut magic_number)?;
 
@@ -168,32 +239,197 @@ pub fn read(reader: &mut io::Read) -> Result<Texture> {
 	let width = header.width;
 	let height = header.height;
 
+	// Every DDS file has a top-level surface; files with mipmaps store the whole chain after it.
+	let num_levels = if header.flags.intersects(HeaderFlags::HAS_MIPMAPS) {
+		max(1, header.mipmap_count) as usize
+	} else {
+		1
+	};
+
 	// Parse the pixel format structure to get information.
-	if pixel_format.flags.intersects(PF_FOUR_CC) {
-		unimplemented!();
+	if pixel_format.flags.intersects(PixelFormatFlags::PF_FOUR_CC) {
+		// Modern DX10 files store the codec in an extended header; legacy files use the FourCC tag.
+		let (algorithm, array_size, dimension, alpha_mode) = if &pixel_format.four_cc == b"DX10" {
+			let mut buf = [0u8; 20];
+			reader.read_exact(&mut buf)?;
+
+			let header_ext: ext::HeaderExt = bincode::deserialize(&buf)?;
+
+			(header_ext.algorithm()?, header_ext.array_size(), header_ext.dimension(), header_ext.alpha_mode())
+		} else {
+			(algorithm_from_four_cc(&pixel_format.four_cc)?, 1, ResourceDimension::Texture2D, AlphaMode::Unknown)
+		};
+
+		let levels = read_levels(reader, width, height, num_levels, |w, h| compressed_size(algorithm, w, h))?;
+
+		let texture = Texture {
+			algorithm: Some(algorithm),
+			array_size, dimension, alpha_mode,
+			levels
+		};
+
+		Ok(texture)
 	} else {
-		let has_alpha = pixel_format.flags.intersects(PF_HAS_ALPHA | PF_ALPHA);
+		let has_alpha = pixel_format.flags.intersects(PixelFormatFlags::PF_HAS_ALPHA | PixelFormatFlags::PF_ALPHA);
 		let bpp = if has_alpha { 32 } else { 24 };
 
-		let data_len = width * height * (bpp / 8);
+		let levels = read_levels(reader, width, height, num_levels, |w, h| (w * h * (bpp / 8)) as usize)?;
 
-		let mut data = Vec::with_capacity(data_len as usize);
+		let texture = Texture {
+			algorithm: None,
+			array_size: 1,
+			dimension: ResourceDimension::Texture2D,
+			alpha_mode: AlphaMode::Unknown,
+			levels
+		};
 
-		unsafe {
-			data.set_len(data_len as usize);
+		Ok(texture)
+	}
+}
+
+/// Writes a texture out as a DDS file.
+///
+/// The `algorithm` argument selects how the surface bytes are interpreted: `Some(_)` writes a
+/// block-compressed file tagged with the matching FourCC, `None` writes plain RGB(A) data with the
+/// appropriate channel masks. BC7 has no legacy FourCC tag, so writing it returns an error. The caller is responsible for providing `texture` data already in the
+/// requested layout (e.g. encoded with `bc1::encode`).
+pub fn write(writer: &mut dyn io::Write, texture: &Texture, algorithm: Option<BCAlgorithm>) -> Result<()> {
+	let (width, height) = texture.dimensions();
+	let mipmap_count = texture.mipmap_levels() as u32;
+	let has_mipmaps = mipmap_count > 1;
+
+	let mut header: Header = unsafe { mem::zeroed() };
+
+	header.size = mem::size_of::<Header>() as u32;
+	header.width = width;
+	header.height = height;
+	header.depth = 1;
+
+	let mut flags = HeaderFlags::REQUIRED;
+	let mut caps = Capabilities::TEXTURE;
+
+	if has_mipmaps {
+		flags |= HeaderFlags::HAS_MIPMAPS;
+		caps |= Capabilities::COMPLEX | Capabilities::MIPMAP;
+		header.mipmap_count = mipmap_count;
+	} else {
+		header.mipmap_count = 1;
+	}
+
+	let mut format: PixelFormat = unsafe { mem::zeroed() };
+	format.size = mem::size_of::<PixelFormat>() as u32;
+
+	if let Some(algorithm) = algorithm {
+		// Compressed surfaces record their byte length (the top-level size) rather than a pitch.
+		flags |= HeaderFlags::COMPRESSED_PITCH;
+		header.pitch_or_linear_size = compressed_size(algorithm, width, height) as u32;
+
+		format.flags = PixelFormatFlags::PF_FOUR_CC;
+		format.four_cc = four_cc_from_algorithm(algorithm)?;
+	} else {
+		// Uncompressed surfaces record the scan line length in bytes.
+		let has_alpha = texture.as_bytes().len() as u32 == width * height * 4;
+		let bpp = if has_alpha { 32 } else { 24 };
+
+		flags |= HeaderFlags::UNCOMPRESSED_PITCH;
+		header.pitch_or_linear_size = width * (bpp / 8);
+
+		format.flags = PixelFormatFlags::PF_RGB;
+		format.rgb_bit_count = bpp;
+		format.red_mask = 0x000000ff;
+		format.green_mask = 0x0000ff00;
+		format.blue_mask = 0x00ff0000;
+		if has_alpha {
+			format.flags |= PixelFormatFlags::PF_HAS_ALPHA;
+			format.alpha_mask = 0xff000000;
 		}
+	}
+
+	header.flags = flags;
+	header.format = format;
+	header.caps = caps;
+
+	writer.write_all(b"DDS ")?;
+
+	let bound = bincode::Bounded(mem::size_of::<Header>() as u64);
+	let bytes = bincode::serialize(&header, bound)?;
+	writer.write_all(&bytes)?;
+
+	for n in 0..texture.mipmap_levels() {
+		let (data, _, _) = texture.level(n);
+		writer.write_all(data)?;
+	}
+
+	Ok(())
+}
+
+// Maps a block-compression algorithm to the legacy FourCC tag used to denote it.
+fn four_cc_from_algorithm(algorithm: BCAlgorithm) -> Result<[u8; 4]> {
+	match algorithm {
+		BCAlgorithm::BC1 => Ok(*b"DXT1"),
+		BCAlgorithm::BC2 => Ok(*b"DXT3"),
+		BCAlgorithm::BC3 => Ok(*b"DXT5"),
+		BCAlgorithm::BC4 => Ok(*b"ATI1"),
+		BCAlgorithm::BC5 => Ok(*b"ATI2"),
+		BCAlgorithm::BC7 => Err(Error::FormatError("BC7 has no legacy FourCC tag".to_string()))
+	}
+}
+
+// Reads `num_levels` successive surfaces from the reader, halving the dimensions (flooring at 1)
+// after each one. `surface_size` returns the byte length of a surface given its dimensions.
+fn read_levels<F>(reader: &mut dyn io::Read, width: u32, height: u32, num_levels: usize, surface_size: F) -> Result<Vec<Level>>
+	where F: Fn(u32, u32) -> usize
+{
+	let mut levels = Vec::with_capacity(num_levels);
+
+	let mut width = width;
+	let mut height = height;
+
+	for _ in 0..num_levels {
+		let mut data = vec![0u8; surface_size(width, height)];
 
 		reader.read_exact(&mut data)?;
 
-		let texture = Texture {
-			width, height,
-			data
-		};
+		levels.push(Level { width, height, data });
 
-		Ok(texture)
+		width = max(1, width / 2);
+		height = max(1, height / 2);
+	}
+
+	Ok(levels)
+}
+
+// Maps a legacy FourCC tag to the block-compression algorithm it denotes.
+fn algorithm_from_four_cc(four_cc: &[u8; 4]) -> Result<BCAlgorithm> {
+	if four_cc == b"DXT1" {
+		Ok(BCAlgorithm::BC1)
+	} else if four_cc == b"DXT2" || four_cc == b"DXT3" {
+		Ok(BCAlgorithm::BC2)
+	} else if four_cc == b"DXT4" || four_cc == b"DXT5" {
+		Ok(BCAlgorithm::BC3)
+	} else if four_cc == b"ATI1" || four_cc == b"BC4U" {
+		Ok(BCAlgorithm::BC4)
+	} else if four_cc == b"ATI2" || four_cc == b"BC5U" {
+		Ok(BCAlgorithm::BC5)
+	} else {
+		let tag = String::from_utf8_lossy(four_cc);
+		Err(Error::FormatError(format!("Unsupported FourCC tag: {}", tag)))
 	}
 }
 
+// Size in bytes of a single compressed surface with the given dimensions.
+fn compressed_size(algorithm: BCAlgorithm, width: u32, height: u32) -> usize {
+	let block_bytes = match algorithm {
+		BCAlgorithm::BC1 | BCAlgorithm::BC4 => 8,
+		BCAlgorithm::BC2 | BCAlgorithm::BC3 | BCAlgorithm::BC5 | BCAlgorithm::BC7 => 16
+	};
+
+	let columns = ((width + 3) / 4).max(1);
+	let rows = ((height + 3) / 4).max(1);
+
+	(columns * rows) as usize * block_bytes
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -258,7 +494,7 @@ mod tests {
 		}
 	}
 
-	use ::image;
+	extern crate image;
 
 	fn data_dir() -> PathBuf {
 		Path::new(env!("CARGO_MANIFEST_DIR")).join("data")
@@ -286,6 +522,8 @@ mod tests {
 
 		let mut bmp = image::bmp::BMPEncoder::new(&mut output);
 
-		let _ = bmp.encode(&texture.data, texture.width, texture.height, image::ColorType::RGBA(8)).unwrap();
+		let (width, height) = texture.dimensions();
+
+		let _ = bmp.encode(texture.as_bytes(), width, height, image::ColorType::RGBA(8)).unwrap();
 	}
 }
@@ -0,0 +1,124 @@
+//! # Reference
+//! See:
+//! - [KTX File Format Specification](https://www.khronos.org/registry/KTX/specs/1.0/ktxspec_v1.html)
+
+use std::io;
+use std::cmp::max;
+use super::{Error, Result};
+use super::dds::{Texture, ResourceDimension, AlphaMode};
+use BCAlgorithm;
+
+// The 12-byte identifier found at the start of every KTX file.
+const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+// The value of the endianness marker when read in the byte order the file was written in.
+const ENDIAN_REFERENCE: u32 = 0x0403_0201;
+
+/// Reads a KTX file.
+pub fn read(reader: &mut dyn io::Read) -> Result<Texture> {
+	{
+		let mut identifier = [0u8; 12];
+		reader.read_exact(&mut identifier)?;
+
+		if identifier != IDENTIFIER {
+			return Err(Error::FormatError("KTX identifier not found".to_string()));
+		}
+	}
+
+	// The endianness marker tells us in which byte order the remaining integer fields are stored.
+	let big_endian = {
+		let mut buf = [0u8; 4];
+		reader.read_exact(&mut buf)?;
+
+		let little = u32::from(buf[0]) | (u32::from(buf[1]) << 8) | (u32::from(buf[2]) << 16) | (u32::from(buf[3]) << 24);
+
+		if little == ENDIAN_REFERENCE {
+			false
+		} else if little.swap_bytes() == ENDIAN_REFERENCE {
+			true
+		} else {
+			return Err(Error::FormatError("KTX endianness marker is invalid".to_string()));
+		}
+	};
+
+	let read_u32 = |reader: &mut dyn io::Read| -> Result<u32> {
+		let mut buf = [0u8; 4];
+		reader.read_exact(&mut buf)?;
+
+		let value = u32::from(buf[0]) | (u32::from(buf[1]) << 8) | (u32::from(buf[2]) << 16) | (u32::from(buf[3]) << 24);
+
+		Ok(if big_endian { value.swap_bytes() } else { value })
+	};
+
+	// The header is a fixed sequence of integer fields; we only need a handful of them.
+	let _gl_type = read_u32(reader)?;
+	let _gl_type_size = read_u32(reader)?;
+	let _gl_format = read_u32(reader)?;
+	let gl_internal_format = read_u32(reader)?;
+	let _gl_base_internal_format = read_u32(reader)?;
+	let pixel_width = read_u32(reader)?;
+	let pixel_height = read_u32(reader)?;
+	let _pixel_depth = read_u32(reader)?;
+	let number_of_array_elements = read_u32(reader)?;
+	let _number_of_faces = read_u32(reader)?;
+	let number_of_mipmap_levels = read_u32(reader)?;
+	let bytes_of_key_value_data = read_u32(reader)?;
+
+	let algorithm = algorithm_from_gl_format(gl_internal_format)?;
+
+	let skip = |reader: &mut dyn io::Read, count: u32| -> Result<()> {
+		let mut buf = vec![0u8; count as usize];
+		reader.read_exact(&mut buf)?;
+		Ok(())
+	};
+
+	// Skip the key/value metadata block; its contents are not needed for decoding.
+	skip(reader, bytes_of_key_value_data)?;
+
+	// Cube maps and 1D textures store a height of zero; treat those as a single row.
+	let width = max(1, pixel_width);
+	let height = max(1, pixel_height);
+	let num_levels = max(1, number_of_mipmap_levels) as usize;
+
+	let mut levels = Vec::with_capacity(num_levels);
+
+	let mut width = width;
+	let mut height = height;
+
+	for _ in 0..num_levels {
+		// Each level is prefixed by the size in bytes of one image, padded to a 4-byte boundary.
+		let image_size = read_u32(reader)?;
+
+		let mut data = vec![0u8; image_size as usize];
+		reader.read_exact(&mut data)?;
+
+		// Skip the mip padding that rounds each level up to a multiple of four bytes.
+		let padding = 3 - ((image_size + 3) % 4);
+		skip(reader, padding)?;
+
+		levels.push((width, height, data));
+
+		width = max(1, width / 2);
+		height = max(1, height / 2);
+	}
+
+	let array_size = max(1, number_of_array_elements);
+
+	Ok(Texture::from_levels(Some(algorithm), array_size, ResourceDimension::Texture2D, AlphaMode::Unknown, levels))
+}
+
+// Maps an OpenGL `glInternalFormat` constant to the block-compression algorithm it denotes.
+fn algorithm_from_gl_format(format: u32) -> Result<BCAlgorithm> {
+	match format {
+		// GL_EXT_texture_compression_s3tc
+		0x83F0 | 0x83F1 => Ok(BCAlgorithm::BC1),
+		0x83F2 => Ok(BCAlgorithm::BC2),
+		0x83F3 => Ok(BCAlgorithm::BC3),
+		// GL_ARB_texture_compression_rgtc
+		0x8DBB => Ok(BCAlgorithm::BC4),
+		0x8DBD => Ok(BCAlgorithm::BC5),
+		// GL_ARB_texture_compression_bptc
+		0x8E8C => Ok(BCAlgorithm::BC7),
+		other => Err(Error::FormatError(format!("Unsupported glInternalFormat: {:#x}", other)))
+	}
+}
@@ -3,3 +3,7 @@ use super::error::{Error, Result};
 /// Support for reading and writing DDS (Direct Draw Surface) files,
 /// most commonly used by DirectX applications.
 pub mod dds;
+
+/// Support for reading KTX (Khronos Texture) files,
+/// the container format used by OpenGL and Vulkan applications.
+pub mod ktx;
@@ -18,13 +18,30 @@ extern crate bincode;
 #[macro_use]
 extern crate serde_derive;
 
+// Optional data parallelism for block decoding, enabled by the `parallel` feature.
+#[cfg(feature = "parallel")]
+extern crate rayon;
+
 /// Enum containing all supported block-compression algorithms.
 #[derive(Copy, Clone)]
 pub enum BCAlgorithm {
 	/// Block compression 1, stores RGB data, with an optional 1-bit alpha.
 	/// This is the recommended format for most textures, providing best compression,
 	/// while the others should be used in special cases, as documented.
-	BC1
+	BC1,
+	/// Block compression 2, stores RGB data like BC1 plus a block of explicit 4-bit alpha.
+	/// Prefer BC3 unless the alpha channel has sharp, non-interpolatable transitions.
+	BC2,
+	/// Block compression 3, stores RGB data like BC1 plus a block of interpolated alpha.
+	/// This is the recommended format for textures with a smooth alpha channel.
+	BC3,
+	/// Block compression 4, stores a single interpolated channel (e.g. a height or gloss map).
+	BC4,
+	/// Block compression 5, stores two interpolated channels, most often a normal map.
+	BC5,
+	/// Block compression 7, stores high-quality RGB or RGBA data. DDS files can only store it
+	/// with the DX10 extended header.
+	BC7
 }
 
 mod error;
@@ -34,6 +51,18 @@ pub use error::{Error, Result};
 /// BC1 stores compressed RGB data, with an optional 1-bit alpha channel.
 pub mod bc1;
 
+/// BC2 stores BC1 color data followed by a block of explicit 4-bit alpha.
+pub mod bc2;
+
+/// BC3 stores BC1 color data followed by a block of interpolated alpha.
+pub mod bc3;
+
+/// BC4 stores a single interpolated channel.
+pub mod bc4;
+
+/// BC5 stores two interpolated channels.
+pub mod bc5;
+
 /// Tools to operate on file formats that store block-compressed data.
 ///
 /// This includes file formats such as *.dds, *.ktx.